@@ -24,6 +24,10 @@ struct CfgenInput {
     pub filename: Option<String>,
     pub generate_test: Option<bool>,
     pub format: Option<Format>,
+    pub layered: bool,
+    pub env_prefix: Option<String>,
+    pub aliases: Option<String>,
+    pub store: bool,
 }
 
 impl darling::FromMeta for Format {
@@ -31,6 +35,8 @@ impl darling::FromMeta for Format {
         match value {
             "yaml" => Ok(Format::Yaml),
             "toml" => Ok(Format::Toml),
+            "json" => Ok(Format::Json),
+            "ron" => Ok(Format::Ron),
             _ => Err(darling::error::Error::unknown_value("Unknown value")),
         }
     }
@@ -61,10 +67,19 @@ pub fn cfgen(tokens: TokenStream) -> TokenStream {
         .default_config_ident
         .as_ref()
         .map(|_| gen_impl_cfgen_default(&input, &opt));
+    let impl_cfgen_store = opt.store.then(|| gen_impl_cfgen_store(&input, &opt));
+    let impl_cfgen_layered = opt.layered.then(|| gen_impl_cfgen_layered(&input, &opt));
+    let impl_cfgen_env = opt
+        .env_prefix
+        .as_ref()
+        .map(|_| gen_impl_cfgen_env(&input, &opt));
 
     TokenStream::from(quote! {
         #impl_cfgen
         #impl_cfgen_default
+        #impl_cfgen_store
+        #impl_cfgen_layered
+        #impl_cfgen_env
     })
 }
 
@@ -95,10 +110,14 @@ fn gen_impl_cfgen(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2::TokenSt
             }
 
             fn load() -> Result<Self, ::cfgen::Error> {
-                let cont = ::std::fs::read_to_string(Self::path())
-                    .map_err(|e| ::cfgen::Error::IoRead(e, Self::path().to_owned()))?;
+                Self::load_from(Self::path())
+            }
+
+            fn load_from(path: &std::path::Path) -> Result<Self, ::cfgen::Error> {
+                let cont = ::std::fs::read_to_string(path)
+                    .map_err(|e| ::cfgen::Error::IoRead(e, path.to_owned()))?;
 
-                #deserialize(&cont).map_err(|e| #fmt_error(e, Self::path().to_owned()))
+                #deserialize(&cont).map_err(|e| #fmt_error(e, path.to_owned()))
             }
         }
     }
@@ -115,6 +134,7 @@ fn gen_impl_cfgen_default(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2:
     );
 
     let deserialize = cfg_opt.format.deserialize_from_str();
+    let aliases = &cfg_opt.aliases;
 
     let test = if cfg_opt.generate_test {
         Some(quote! {
@@ -130,6 +150,36 @@ fn gen_impl_cfgen_default(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2:
     quote! {
         impl #impl_generics ::cfgen::CfgenDefault for #name #ty_generics #where_clause {
             fn load_or_write_default() -> Result<(::cfgen::ConfigLoad, Self), ::cfgen::Error> {
+                let primary = Self::path();
+                let aliases: &[&str] = &[#(#aliases),*];
+
+                let mut found: Vec<::std::path::PathBuf> = Vec::new();
+                if primary.is_file() {
+                    found.push(primary.to_owned());
+                }
+                if let Some(dir) = primary.parent() {
+                    for alias in aliases {
+                        let candidate = dir.join(alias);
+                        if candidate.is_file() {
+                            found.push(candidate);
+                        }
+                    }
+                }
+                if found.len() > 1 {
+                    return Err(::cfgen::Error::AmbiguousSource(
+                        found[0].clone(),
+                        found[1].clone(),
+                    ));
+                }
+
+                // A lone alias is the only candidate on disk (e.g. the pre-rename filename);
+                // load it directly instead of falling through to `primary`, which would look
+                // missing and get silently overwritten with the default.
+                if let Some(source) = found.into_iter().next().filter(|p| p.as_path() != primary) {
+                    return Self::load_from(&source)
+                        .map(|ret| (::cfgen::ConfigLoad::Loaded, ret));
+                }
+
                 match Self::load() {
                     Ok(ret) => Ok((::cfgen::ConfigLoad::Loaded, ret)),
                     Err(::cfgen::Error::IoRead(e, path)) => {
@@ -150,12 +200,16 @@ fn gen_impl_cfgen_default(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2:
             }
 
             fn write_default() -> Result<Self, ::cfgen::Error> {
+                Self::write_default_to(Self::path())
+            }
+
+            fn write_default_to(path: &std::path::Path) -> Result<Self, ::cfgen::Error> {
                 use ::std::fs;
-                let parent = Self::path().parent().unwrap();
+                let parent = path.parent().unwrap();
                 fs::create_dir_all(parent).map_err(|e| ::cfgen::Error::MakeDir(e, parent.to_owned()))?;
-                fs::write(Self::path(), #default_ident)
-                    .map_err(|e| ::cfgen::Error::IoWrite(e, Self::path().to_owned()))?;
-                Self::load()
+                fs::write(path, #default_ident)
+                    .map_err(|e| ::cfgen::Error::IoWrite(e, path.to_owned()))?;
+                Self::load_from(path)
             }
         }
 
@@ -163,10 +217,145 @@ fn gen_impl_cfgen_default(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2:
     }
 }
 
+fn gen_impl_cfgen_store(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let serialize = cfg_opt.format.serialize_to_string(&quote! { self });
+    let serialize_error = cfg_opt.format.error_serialize();
+
+    quote! {
+        impl #impl_generics ::cfgen::CfgenStore for #name #ty_generics #where_clause {
+            fn store(&self) -> Result<(), ::cfgen::Error> {
+                let parent = Self::path().parent().unwrap();
+                ::std::fs::create_dir_all(parent)
+                    .map_err(|e| ::cfgen::Error::MakeDir(e, parent.to_owned()))?;
+
+                let serialized =
+                    #serialize.map_err(|e| #serialize_error(e, Self::path().to_owned()))?;
+
+                ::cfgen::atomic_write(Self::path(), serialized)
+                    .map_err(|e| ::cfgen::Error::IoWrite(e, Self::path().to_owned()))
+            }
+        }
+    }
+}
+
+fn gen_impl_cfgen_layered(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2::TokenStream {
+    let CfgOpt {
+        org,
+        qualifier,
+        application,
+        filename,
+        ..
+    } = cfg_opt;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fmt_error = cfg_opt.format.error();
+    let value_type = cfg_opt.format.value_type();
+    let parse_value = cfg_opt.format.deserialize_from_str();
+    let merge_value = cfg_opt.format.merge_value();
+
+    quote! {
+        impl #impl_generics ::cfgen::CfgenLayered for #name #ty_generics #where_clause {
+            fn load_layered() -> Result<(Self, Vec<::std::path::PathBuf>), ::cfgen::Error> {
+                let candidates = vec![
+                    ::cfgen::system_config_dir(#qualifier, #org, #application).join(#filename),
+                    Self::path().to_owned(),
+                ]
+                .into_iter()
+                .chain(::cfgen::find_local_config(#filename));
+
+                let mut used = Vec::new();
+                let mut merged: Option<#value_type> = None;
+
+                for candidate in candidates {
+                    let cont = match ::std::fs::read_to_string(&candidate) {
+                        Ok(cont) => cont,
+                        Err(e) if e.kind() == ::std::io::ErrorKind::NotFound => continue,
+                        Err(e) => return Err(::cfgen::Error::IoRead(e, candidate)),
+                    };
+
+                    let value: #value_type =
+                        #parse_value(&cont).map_err(|e| #fmt_error(e, candidate.clone()))?;
+
+                    merged = Some(match merged {
+                        Some(base) => #merge_value(base, value),
+                        None => value,
+                    });
+                    used.push(candidate);
+                }
+
+                let merged = merged.ok_or_else(|| {
+                    ::cfgen::Error::IoRead(
+                        ::std::io::Error::from(::std::io::ErrorKind::NotFound),
+                        Self::path().to_owned(),
+                    )
+                })?;
+
+                let config = <Self as ::serde::Deserialize>::deserialize(merged)
+                    .map_err(|e| #fmt_error(e, Self::path().to_owned()))?;
+
+                Ok((config, used))
+            }
+        }
+    }
+}
+
+fn gen_impl_cfgen_env(input: &DeriveInput, cfg_opt: &CfgOpt) -> proc_macro2::TokenStream {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let env_prefix = cfg_opt
+        .env_prefix
+        .as_ref()
+        .expect("gen_impl_cfgen_env called without an env_prefix");
+    let fmt_error = cfg_opt.format.error();
+    let value_type = cfg_opt.format.value_type();
+    let parse_value = cfg_opt.format.deserialize_from_str();
+    let set_path = cfg_opt.format.set_path();
+
+    quote! {
+        impl #impl_generics ::cfgen::CfgenEnv for #name #ty_generics #where_clause {
+            fn load_with_env() -> Result<Self, ::cfgen::Error> {
+                let cont = ::std::fs::read_to_string(Self::path())
+                    .map_err(|e| ::cfgen::Error::IoRead(e, Self::path().to_owned()))?;
+
+                let mut value: #value_type =
+                    #parse_value(&cont).map_err(|e| #fmt_error(e, Self::path().to_owned()))?;
+
+                // Applied one override at a time so a deserialization failure can be blamed on
+                // the specific variable that caused it, rather than guessed at after the fact.
+                let mut deserializes =
+                    <Self as ::serde::Deserialize>::deserialize(value.clone()).is_ok();
+                for (var, path, raw) in ::cfgen::matching_env_vars(#env_prefix) {
+                    #set_path(&mut value, &path, &raw);
+                    match <Self as ::serde::Deserialize>::deserialize(value.clone()) {
+                        Ok(_) => deserializes = true,
+                        Err(e) if deserializes => {
+                            return Err(::cfgen::Error::Env {
+                                var,
+                                source: ::std::boxed::Box::new(e),
+                            });
+                        }
+                        Err(_) => {}
+                    }
+                }
+
+                <Self as ::serde::Deserialize>::deserialize(value)
+                    .map_err(|e| #fmt_error(e, Self::path().to_owned()))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Format {
     Yaml,
     Toml,
+    Json,
+    Ron,
 }
 
 impl Format {
@@ -174,6 +363,8 @@ impl Format {
         match self {
             Format::Yaml => "config.yml",
             Format::Toml => "config.toml",
+            Format::Json => "config.json",
+            Format::Ron => "config.ron",
         }
     }
 
@@ -181,6 +372,8 @@ impl Format {
         match self {
             Format::Yaml => quote! { ::cfgen::serde_yaml::from_str },
             Format::Toml => quote! { ::cfgen::toml::from_str },
+            Format::Json => quote! { ::cfgen::serde_json::from_str },
+            Format::Ron => quote! { ::cfgen::ron::de::from_str },
         }
     }
 
@@ -188,6 +381,55 @@ impl Format {
         match self {
             Format::Yaml => quote! { ::cfgen::Error::Yaml },
             Format::Toml => quote! { ::cfgen::Error::Toml },
+            Format::Json => quote! { ::cfgen::Error::Json },
+            Format::Ron => quote! { ::cfgen::Error::Ron },
+        }
+    }
+
+    fn serialize_to_string(&self, value: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match self {
+            Format::Yaml => quote! { ::cfgen::serde_yaml::to_string(#value) },
+            Format::Toml => quote! { ::cfgen::toml::to_string_pretty(#value) },
+            Format::Json => quote! { ::cfgen::serde_json::to_string_pretty(#value) },
+            Format::Ron => quote! {
+                ::cfgen::ron::ser::to_string_pretty(#value, ::cfgen::ron::ser::PrettyConfig::default())
+            },
+        }
+    }
+
+    fn error_serialize(&self) -> proc_macro2::TokenStream {
+        match self {
+            Format::Yaml => quote! { ::cfgen::Error::YamlSerialize },
+            Format::Toml => quote! { ::cfgen::Error::TomlSerialize },
+            Format::Json => quote! { ::cfgen::Error::JsonSerialize },
+            Format::Ron => quote! { ::cfgen::Error::RonSerialize },
+        }
+    }
+
+    fn value_type(&self) -> proc_macro2::TokenStream {
+        match self {
+            Format::Yaml => quote! { ::cfgen::serde_yaml::Value },
+            Format::Toml => quote! { ::cfgen::toml::Value },
+            Format::Json => quote! { ::cfgen::serde_json::Value },
+            Format::Ron => quote! { ::cfgen::ron::Value },
+        }
+    }
+
+    fn merge_value(&self) -> proc_macro2::TokenStream {
+        match self {
+            Format::Yaml => quote! { ::cfgen::merge_yaml_value },
+            Format::Toml => quote! { ::cfgen::merge_toml_value },
+            Format::Json => quote! { ::cfgen::merge_json_value },
+            Format::Ron => quote! { ::cfgen::merge_ron_value },
+        }
+    }
+
+    fn set_path(&self) -> proc_macro2::TokenStream {
+        match self {
+            Format::Yaml => quote! { ::cfgen::yaml_set_path },
+            Format::Toml => quote! { ::cfgen::toml_set_path },
+            Format::Json => quote! { ::cfgen::json_set_path },
+            Format::Ron => quote! { ::cfgen::ron_set_path },
         }
     }
 }
@@ -201,6 +443,14 @@ cfg_if! {
         fn default_format() -> Format {
             Format::Yaml
         }
+    } else if #[cfg(feature = "json")] {
+        fn default_format() -> Format {
+            Format::Json
+        }
+    } else if #[cfg(feature = "ron")] {
+        fn default_format() -> Format {
+            Format::Ron
+        }
     } else {
         fn default_format() -> Format {
             panic!("cfgen needs at least one format feature enabled")
@@ -217,6 +467,10 @@ struct CfgOpt {
     pub filename: String,
     pub format: Format,
     pub generate_test: bool,
+    pub layered: bool,
+    pub env_prefix: Option<String>,
+    pub aliases: Vec<String>,
+    pub store: bool,
 }
 
 macro_rules! optional_unpack {
@@ -239,8 +493,19 @@ impl From<CfgenInput> for CfgOpt {
             application,
             filename,
             format,
-            generate_test
+            generate_test,
+            env_prefix
         );
+        ret.layered = other.layered;
+        ret.store = other.store;
+        if let Some(aliases) = other.aliases {
+            ret.aliases = aliases
+                .split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned)
+                .collect();
+        }
         ret
     }
 }
@@ -270,6 +535,10 @@ impl Default for CfgOpt {
             filename: format.default_filename().to_owned(),
             format,
             generate_test: true,
+            layered: false,
+            store: false,
+            env_prefix: None,
+            aliases: Vec::new(),
         }
     }
 }
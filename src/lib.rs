@@ -1,7 +1,9 @@
 /*!
 This crate allows you to derive commonly used functions for configuration files.
-It will derive [Cfgen](Cfgen) and optionally [CfgenDefault](CfgenDefault)
-depending on the args passed to cfgen.
+It will derive [Cfgen](Cfgen), optionally [CfgenDefault](CfgenDefault)
+depending on the args passed to cfgen, and [CfgenStore](CfgenStore) when the struct
+opts in with `#[cfgen(store)]` (which additionally requires the struct to derive
+`serde::Serialize`).
 
 # Usage
 ```
@@ -39,9 +41,22 @@ All keys for the derive macro are optional
              Also generates a test that asserts that the default config parses.
 - `generate_test`: Wether to generate an automatic test that tests if the default
                    config is deserializeable. Defaults to true
+- `layered`: Additionally derives [CfgenLayered](CfgenLayered), which merges a system-wide,
+             user and project-local config file instead of reading a single file.
+             Defaults to false
+- `env_prefix`: Additionally derives [CfgenEnv](CfgenEnv), which lets environment variables
+                named `$ENV_PREFIX_$FIELD` override individual keys of the loaded config.
+                Not set by default, so no env overrides are applied
+- `aliases`: Comma separated list of extra filenames that are considered candidates for the
+             config file, e.g. a legacy name from before a rename. `load_or_write_default`
+             errors with [Error::AmbiguousSource](Error::AmbiguousSource) if more than one
+             candidate is present on disk at once. Empty by default
+- `store`: Additionally derives [CfgenStore](CfgenStore), which adds a `store()` method to
+           serialize the struct back to `Cfgen::path()`. The struct must also derive
+           `serde::Serialize`, since `store()` needs it. Defaults to false
 
-All config formats are optional cargo features, if you want to use toml/yaml configuration
-add "with-toml"/"yaml" to the enabled features of this crate.
+All config formats are optional cargo features, if you want to use toml/yaml/json/ron
+configuration add "with-toml"/"yaml"/"json"/"ron" to the enabled features of this crate.
 
 # Config path construction
 The config path is constructed with the
@@ -64,11 +79,17 @@ pub use directories;
 #[doc(hide)]
 pub use once_cell;
 #[doc(hide)]
+#[cfg(feature = "json")]
+pub use serde_json;
+#[doc(hide)]
 #[cfg(feature = "yaml")]
 pub use serde_yaml;
 #[doc(hide)]
 #[cfg(feature = "with-toml")]
 pub use toml;
+#[doc(hide)]
+#[cfg(feature = "ron")]
+pub use ron;
 
 /// Basic functions to read a config.
 pub trait Cfgen
@@ -81,6 +102,10 @@ where
     /// Load config from config dir. Errors with IoRead when the file can't be read, and with
     /// either Toml or Yaml when the file can't be deserialized.
     fn load() -> Result<Self, Error>;
+
+    /// Loads the config from an explicit path, bypassing [Cfgen::path](Cfgen::path) and any
+    /// configured `aliases` entirely. Useful for a `--config` CLI flag or tests.
+    fn load_from(path: &Path) -> Result<Self, Error>;
 }
 
 /// Configuration file with a default config
@@ -89,11 +114,48 @@ pub trait CfgenDefault: Cfgen {
     /// written.
     fn write_default() -> Result<Self, Error>;
 
+    /// Writes the default config to an explicit path, bypassing [Cfgen::path](Cfgen::path).
+    /// Useful for a `--config` CLI flag or tests.
+    fn write_default_to(path: &Path) -> Result<Self, Error>;
+
     /// If the config file doesn't exist, writes the default to Cfgen::path() and then tries to
-    /// load the default config on disk. Returns a [ConfigLoad](ConfigLoad)
+    /// load the default config on disk. Returns a [ConfigLoad](ConfigLoad). Errors with
+    /// [Error::AmbiguousSource](Error::AmbiguousSource) when more than one candidate config
+    /// file (the default filename or one of its configured `aliases`) is present at once.
     fn load_or_write_default() -> Result<(ConfigLoad, Self), Error>;
 }
 
+/// Configuration file that can be serialized back to disk. Derived alongside [Cfgen](Cfgen)
+/// when `#[cfgen(store)]` is passed to the derive macro. The struct must also derive
+/// `serde::Serialize`, or the generated impl won't compile.
+pub trait CfgenStore: Cfgen {
+    /// Serializes `self` with the format's pretty serializer and atomically writes the result
+    /// to [Cfgen::path](Cfgen::path), creating parent directories as needed.
+    fn store(&self) -> Result<(), Error>;
+}
+
+/// Configuration loaded by merging several files in precedence order. Derived alongside
+/// [Cfgen](Cfgen) when `#[cfgen(layered)]` is passed to the derive macro.
+pub trait CfgenLayered: Cfgen {
+    /// Loads and deep-merges the system-wide, user and project-local config files, in that
+    /// precedence order: later layers override individual keys of earlier ones rather than
+    /// replacing the whole file. Missing files are skipped; a present but unparseable file is
+    /// an error. Returns the merged config together with the paths that contributed to it.
+    fn load_layered() -> Result<(Self, Vec<PathBuf>), Error>;
+}
+
+/// Configuration loaded from a file with individual keys overridable via environment
+/// variables. Derived alongside [Cfgen](Cfgen) when `#[cfgen(env_prefix = "...")]` is passed
+/// to the derive macro.
+pub trait CfgenEnv: Cfgen {
+    /// Loads the config file like [Cfgen::load](Cfgen::load), then overlays any environment
+    /// variable named `<PREFIX>_<FIELD>` (nested fields joined by `__`) onto the parsed value,
+    /// one variable at a time, re-deserializing after each to take precedence over the file.
+    /// If deserialization starts failing after a particular override is applied, that
+    /// variable is reported via [Error::Env](Error::Env).
+    fn load_with_env() -> Result<Self, Error>;
+}
+
 /// All possible errors this crate can return.
 #[derive(Debug)]
 pub enum Error {
@@ -113,6 +175,44 @@ pub enum Error {
     /// Error caused by failed yaml deserialization
     #[cfg(feature = "yaml")]
     Yaml(serde_yaml::Error, PathBuf),
+
+    /// Error caused by failed json deserialization
+    #[cfg(feature = "json")]
+    Json(serde_json::Error, PathBuf),
+
+    /// Error caused by failed ron deserialization
+    #[cfg(feature = "ron")]
+    Ron(ron::de::Error, PathBuf),
+
+    /// Error caused by failed toml serialization
+    #[cfg(feature = "with-toml")]
+    TomlSerialize(toml::ser::Error, PathBuf),
+
+    /// Error caused by failed yaml serialization
+    #[cfg(feature = "yaml")]
+    YamlSerialize(serde_yaml::Error, PathBuf),
+
+    /// Error caused by failed json serialization
+    #[cfg(feature = "json")]
+    JsonSerialize(serde_json::Error, PathBuf),
+
+    /// Error caused by failed ron serialization
+    #[cfg(feature = "ron")]
+    RonSerialize(ron::ser::Error, PathBuf),
+
+    /// Failed to apply an environment variable override. Overrides are applied one at a time,
+    /// each followed by a deserialization attempt, so `var` is the specific environment
+    /// variable whose application caused deserialization to start failing.
+    Env {
+        /// Name of the environment variable
+        var: String,
+        /// Underlying deserialization error
+        source: Box<dyn std::error::Error + Send + Sync + 'static>,
+    },
+
+    /// More than one candidate config file is present at once, e.g. both `config.toml` and
+    /// `config.yml`, or both a legacy filename and the current one.
+    AmbiguousSource(PathBuf, PathBuf),
 }
 
 impl std::error::Error for Error {
@@ -125,6 +225,20 @@ impl std::error::Error for Error {
             Error::Toml(e, _) => Some(e),
             #[cfg(feature = "yaml")]
             Error::Yaml(e, _) => Some(e),
+            #[cfg(feature = "json")]
+            Error::Json(e, _) => Some(e),
+            #[cfg(feature = "ron")]
+            Error::Ron(e, _) => Some(e),
+            #[cfg(feature = "with-toml")]
+            Error::TomlSerialize(e, _) => Some(e),
+            #[cfg(feature = "yaml")]
+            Error::YamlSerialize(e, _) => Some(e),
+            #[cfg(feature = "json")]
+            Error::JsonSerialize(e, _) => Some(e),
+            #[cfg(feature = "ron")]
+            Error::RonSerialize(e, _) => Some(e),
+            Error::Env { source, .. } => Some(source.as_ref()),
+            Error::AmbiguousSource(..) => None,
         }
     }
 }
@@ -161,6 +275,57 @@ impl fmt::Display for Error {
                 path.display(),
                 e
             ),
+            #[cfg(feature = "json")]
+            Error::Json(e, path) => write!(
+                formatter,
+                "Can't read config from {}: {}",
+                path.display(),
+                e
+            ),
+            #[cfg(feature = "ron")]
+            Error::Ron(e, path) => write!(
+                formatter,
+                "Can't read config from {}: {}",
+                path.display(),
+                e
+            ),
+            #[cfg(feature = "with-toml")]
+            Error::TomlSerialize(e, path) => write!(
+                formatter,
+                "Can't serialize config for {}: {}",
+                path.display(),
+                e
+            ),
+            #[cfg(feature = "yaml")]
+            Error::YamlSerialize(e, path) => write!(
+                formatter,
+                "Can't serialize config for {}: {}",
+                path.display(),
+                e
+            ),
+            #[cfg(feature = "json")]
+            Error::JsonSerialize(e, path) => write!(
+                formatter,
+                "Can't serialize config for {}: {}",
+                path.display(),
+                e
+            ),
+            #[cfg(feature = "ron")]
+            Error::RonSerialize(e, path) => write!(
+                formatter,
+                "Can't serialize config for {}: {}",
+                path.display(),
+                e
+            ),
+            Error::Env { var, source } => {
+                write!(formatter, "Can't apply env override {}: {}", var, source)
+            }
+            Error::AmbiguousSource(a, b) => write!(
+                formatter,
+                "Found more than one candidate config file: {} and {}",
+                a.display(),
+                b.display()
+            ),
         }
     }
 }
@@ -213,3 +378,487 @@ where
 {
     Option::<PathBuf>::deserialize(deserializer).map(|buf| buf.map(tilde_expand::tilde_expand))
 }
+
+/// Writes `contents` to `path` atomically by first writing to a sibling temporary file and
+/// then renaming it into place. Used by the generated [CfgenStore::store](CfgenStore::store).
+#[doc(hide)]
+pub fn atomic_write(path: &Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let mut tmp_name = path
+        .file_name()
+        .expect("config path needs a filename")
+        .to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    std::fs::write(&tmp_path, contents)?;
+    std::fs::rename(&tmp_path, path)
+}
+
+/// The directory a system-wide config for `application` is expected to live in, used by
+/// [CfgenLayered::load_layered](CfgenLayered::load_layered). `qualifier` is unused; `org` is
+/// only used on platforms that namespace system config by vendor.
+#[doc(hide)]
+#[cfg(unix)]
+pub fn system_config_dir(_qualifier: &str, _org: &str, application: &str) -> PathBuf {
+    PathBuf::from("/etc").join(application)
+}
+
+/// Windows equivalent of the unix `/etc/<application>` system config directory.
+#[doc(hide)]
+#[cfg(windows)]
+pub fn system_config_dir(_qualifier: &str, org: &str, application: &str) -> PathBuf {
+    let program_data =
+        std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+    PathBuf::from(program_data).join(org).join(application)
+}
+
+/// Walks up from the current directory looking for `filename`, the same way cargo and jj find
+/// their project-local config. Returns the first match, closest to the current directory.
+#[doc(hide)]
+pub fn find_local_config(filename: &str) -> Option<PathBuf> {
+    let mut dir = std::env::current_dir().ok()?;
+    loop {
+        let candidate = dir.join(filename);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+
+        if !dir.pop() {
+            return None;
+        }
+    }
+}
+
+/// Recursively merges two toml values, with `overlay` taking precedence over `base` key by
+/// key. Non-table values in `overlay` replace the corresponding value in `base` wholesale.
+#[doc(hide)]
+#[cfg(feature = "with-toml")]
+pub fn merge_toml_value(base: toml::Value, overlay: toml::Value) -> toml::Value {
+    match (base, overlay) {
+        (toml::Value::Table(mut base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(existing) => merge_toml_value(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            toml::Value::Table(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+/// Recursively merges two yaml values, with `overlay` taking precedence over `base` key by
+/// key. Non-mapping values in `overlay` replace the corresponding value in `base` wholesale.
+#[doc(hide)]
+#[cfg(feature = "yaml")]
+pub fn merge_yaml_value(base: serde_yaml::Value, overlay: serde_yaml::Value) -> serde_yaml::Value {
+    match (base, overlay) {
+        (serde_yaml::Value::Mapping(mut base), serde_yaml::Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(existing) => merge_yaml_value(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            serde_yaml::Value::Mapping(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+/// Recursively merges two json values, with `overlay` taking precedence over `base` key by
+/// key. Non-object values in `overlay` replace the corresponding value in `base` wholesale.
+#[doc(hide)]
+#[cfg(feature = "json")]
+pub fn merge_json_value(base: serde_json::Value, overlay: serde_json::Value) -> serde_json::Value {
+    match (base, overlay) {
+        (serde_json::Value::Object(mut base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(existing) => merge_json_value(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            serde_json::Value::Object(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+/// Recursively merges two ron values, with `overlay` taking precedence over `base` key by
+/// key. Non-map values in `overlay` replace the corresponding value in `base` wholesale.
+#[doc(hide)]
+#[cfg(feature = "ron")]
+pub fn merge_ron_value(base: ron::Value, overlay: ron::Value) -> ron::Value {
+    match (base, overlay) {
+        (ron::Value::Map(mut base), ron::Value::Map(overlay)) => {
+            for (key, value) in overlay {
+                let merged = match base.remove(&key) {
+                    Some(existing) => merge_ron_value(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            ron::Value::Map(base)
+        }
+        (_, overlay) => overlay,
+    }
+}
+
+/// Parses a raw environment variable value into the scalar type it looks like (bool, then
+/// integer, then float), falling back to a string if none of those match.
+#[cfg(any(feature = "with-toml", feature = "yaml", feature = "json", feature = "ron"))]
+fn env_scalar_kind(raw: &str) -> EnvScalar {
+    if let Ok(b) = raw.parse::<bool>() {
+        EnvScalar::Bool(b)
+    } else if let Ok(i) = raw.parse::<i64>() {
+        EnvScalar::Int(i)
+    } else if let Ok(f) = raw.parse::<f64>() {
+        EnvScalar::Float(f)
+    } else {
+        EnvScalar::Str(raw.to_owned())
+    }
+}
+
+#[cfg(any(feature = "with-toml", feature = "yaml", feature = "json", feature = "ron"))]
+enum EnvScalar {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+/// Finds every environment variable named `<prefix>_<FIELD>` (nested fields joined by `__`)
+/// and returns, for each match, the variable's name, its field path (lowercased, split on
+/// `__`) and its raw value.
+#[doc(hide)]
+pub fn matching_env_vars(prefix: &str) -> Vec<(String, Vec<String>, String)> {
+    let prefix = format!("{}_", prefix);
+    let mut matches = Vec::new();
+    for (key, raw) in std::env::vars() {
+        if let Some(field_path) = key.strip_prefix(&prefix) {
+            let path: Vec<String> = field_path.split("__").map(str::to_lowercase).collect();
+            matches.push((key.clone(), path, raw));
+        }
+    }
+    matches
+}
+
+/// Overlays a single environment-variable override, addressed by its (already split and
+/// lowercased) field path, onto a toml value.
+#[doc(hide)]
+#[cfg(feature = "with-toml")]
+pub fn toml_set_path(value: &mut toml::Value, path: &[String], raw: &str) {
+    if !matches!(value, toml::Value::Table(_)) {
+        *value = toml::Value::Table(Default::default());
+    }
+    let table = match value {
+        toml::Value::Table(table) => table,
+        _ => unreachable!(),
+    };
+
+    match path {
+        [] => {}
+        [last] => {
+            let scalar = match env_scalar_kind(raw) {
+                EnvScalar::Bool(b) => toml::Value::Boolean(b),
+                EnvScalar::Int(i) => toml::Value::Integer(i),
+                EnvScalar::Float(f) => toml::Value::Float(f),
+                EnvScalar::Str(s) => toml::Value::String(s),
+            };
+            table.insert(last.clone(), scalar);
+        }
+        [head, rest @ ..] => {
+            let entry = table
+                .entry(head.clone())
+                .or_insert_with(|| toml::Value::Table(Default::default()));
+            toml_set_path(entry, rest, raw);
+        }
+    }
+}
+
+/// Overlays a single environment-variable override, addressed by its (already split and
+/// lowercased) field path, onto a yaml value.
+#[doc(hide)]
+#[cfg(feature = "yaml")]
+pub fn yaml_set_path(value: &mut serde_yaml::Value, path: &[String], raw: &str) {
+    if !matches!(value, serde_yaml::Value::Mapping(_)) {
+        *value = serde_yaml::Value::Mapping(Default::default());
+    }
+    let mapping = match value {
+        serde_yaml::Value::Mapping(mapping) => mapping,
+        _ => unreachable!(),
+    };
+
+    match path {
+        [] => {}
+        [last] => {
+            let scalar = match env_scalar_kind(raw) {
+                EnvScalar::Bool(b) => serde_yaml::Value::Bool(b),
+                EnvScalar::Int(i) => serde_yaml::Value::Number(i.into()),
+                EnvScalar::Float(f) => serde_yaml::Value::Number(f.into()),
+                EnvScalar::Str(s) => serde_yaml::Value::String(s),
+            };
+            mapping.insert(serde_yaml::Value::String(last.clone()), scalar);
+        }
+        [head, rest @ ..] => {
+            let key = serde_yaml::Value::String(head.clone());
+            if !mapping.contains_key(&key) {
+                mapping.insert(key.clone(), serde_yaml::Value::Mapping(Default::default()));
+            }
+            yaml_set_path(mapping.get_mut(&key).unwrap(), rest, raw);
+        }
+    }
+}
+
+/// Overlays a single environment-variable override, addressed by its (already split and
+/// lowercased) field path, onto a json value.
+#[doc(hide)]
+#[cfg(feature = "json")]
+pub fn json_set_path(value: &mut serde_json::Value, path: &[String], raw: &str) {
+    if !matches!(value, serde_json::Value::Object(_)) {
+        *value = serde_json::Value::Object(Default::default());
+    }
+    let object = match value {
+        serde_json::Value::Object(object) => object,
+        _ => unreachable!(),
+    };
+
+    match path {
+        [] => {}
+        [last] => {
+            let scalar = match env_scalar_kind(raw) {
+                EnvScalar::Bool(b) => serde_json::Value::Bool(b),
+                EnvScalar::Int(i) => serde_json::Value::Number(i.into()),
+                EnvScalar::Float(f) => serde_json::Number::from_f64(f)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or(serde_json::Value::Null),
+                EnvScalar::Str(s) => serde_json::Value::String(s),
+            };
+            object.insert(last.clone(), scalar);
+        }
+        [head, rest @ ..] => {
+            let entry = object
+                .entry(head.clone())
+                .or_insert_with(|| serde_json::Value::Object(Default::default()));
+            json_set_path(entry, rest, raw);
+        }
+    }
+}
+
+/// Overlays a single environment-variable override, addressed by its (already split and
+/// lowercased) field path, onto a ron value.
+#[doc(hide)]
+#[cfg(feature = "ron")]
+pub fn ron_set_path(value: &mut ron::Value, path: &[String], raw: &str) {
+    if !matches!(value, ron::Value::Map(_)) {
+        *value = ron::Value::Map(Default::default());
+    }
+    let map = match value {
+        ron::Value::Map(map) => map,
+        _ => unreachable!(),
+    };
+
+    match path {
+        [] => {}
+        [last] => {
+            let scalar = match env_scalar_kind(raw) {
+                EnvScalar::Bool(b) => ron::Value::Bool(b),
+                EnvScalar::Int(i) => ron::Value::Number(ron::Number::from(i)),
+                EnvScalar::Float(f) => ron::Value::Number(ron::Number::from(f)),
+                EnvScalar::Str(s) => ron::Value::String(s),
+            };
+            map.insert(ron::Value::String(last.clone()), scalar);
+        }
+        [head, rest @ ..] => {
+            let key = ron::Value::String(head.clone());
+            if map.get(&key).is_none() {
+                map.insert(key.clone(), ron::Value::Map(Default::default()));
+            }
+            ron_set_path(map.get_mut(&key).unwrap(), rest, raw);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "with-toml")]
+    #[test]
+    fn merge_toml_value_overlays_keys_and_replaces_scalars() {
+        let base: toml::Value = toml::from_str("a = 1\n[nested]\nx = 1\ny = 2\n").unwrap();
+        let overlay: toml::Value = toml::from_str("b = 2\n[nested]\ny = 3\n").unwrap();
+
+        let merged = merge_toml_value(base, overlay);
+
+        assert_eq!(merged["a"].as_integer(), Some(1));
+        assert_eq!(merged["b"].as_integer(), Some(2));
+        assert_eq!(merged["nested"]["x"].as_integer(), Some(1));
+        assert_eq!(merged["nested"]["y"].as_integer(), Some(3));
+    }
+
+    #[cfg(feature = "with-toml")]
+    #[test]
+    fn merge_toml_value_overlay_scalar_replaces_table() {
+        let base: toml::Value = toml::from_str("[a]\nx = 1\n").unwrap();
+        let overlay: toml::Value = toml::from_str("a = 1\n").unwrap();
+
+        let merged = merge_toml_value(base, overlay);
+
+        assert_eq!(merged["a"].as_integer(), Some(1));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn merge_yaml_value_overlays_keys_and_replaces_scalars() {
+        let base: serde_yaml::Value = serde_yaml::from_str("a: 1\nnested:\n  x: 1\n  y: 2\n").unwrap();
+        let overlay: serde_yaml::Value = serde_yaml::from_str("b: 2\nnested:\n  y: 3\n").unwrap();
+
+        let merged = merge_yaml_value(base, overlay);
+
+        assert_eq!(merged["a"].as_i64(), Some(1));
+        assert_eq!(merged["b"].as_i64(), Some(2));
+        assert_eq!(merged["nested"]["x"].as_i64(), Some(1));
+        assert_eq!(merged["nested"]["y"].as_i64(), Some(3));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn merge_json_value_overlays_keys_and_replaces_scalars() {
+        let base: serde_json::Value =
+            serde_json::from_str(r#"{"a": 1, "nested": {"x": 1, "y": 2}}"#).unwrap();
+        let overlay: serde_json::Value =
+            serde_json::from_str(r#"{"b": 2, "nested": {"y": 3}}"#).unwrap();
+
+        let merged = merge_json_value(base, overlay);
+
+        assert_eq!(merged["a"], 1);
+        assert_eq!(merged["b"], 2);
+        assert_eq!(merged["nested"]["x"], 1);
+        assert_eq!(merged["nested"]["y"], 3);
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn merge_ron_value_overlays_keys_and_replaces_scalars() {
+        let base: ron::Value = ron::de::from_str("{\"a\": 1, \"nested\": {\"x\": 1, \"y\": 2}}").unwrap();
+        let overlay: ron::Value = ron::de::from_str("{\"b\": 2, \"nested\": {\"y\": 3}}").unwrap();
+
+        let merged = merge_ron_value(base, overlay);
+
+        let map = match &merged {
+            ron::Value::Map(map) => map,
+            _ => panic!("expected a ron map"),
+        };
+        assert_eq!(
+            map.get(&ron::Value::String("a".to_owned())),
+            Some(&ron::Value::Number(ron::Number::from(1)))
+        );
+        assert_eq!(
+            map.get(&ron::Value::String("b".to_owned())),
+            Some(&ron::Value::Number(ron::Number::from(2)))
+        );
+        assert_eq!(
+            map.get(&ron::Value::String("nested".to_owned())),
+            Some(&ron::Value::Map({
+                let mut inner = ron::Map::new();
+                inner.insert(
+                    ron::Value::String("x".to_owned()),
+                    ron::Value::Number(ron::Number::from(1)),
+                );
+                inner.insert(
+                    ron::Value::String("y".to_owned()),
+                    ron::Value::Number(ron::Number::from(3)),
+                );
+                inner
+            }))
+        );
+    }
+
+    #[cfg(feature = "with-toml")]
+    #[test]
+    fn toml_set_path_guesses_scalar_kind_and_nests() {
+        let mut value = toml::Value::Table(Default::default());
+        toml_set_path(&mut value, &["nested".to_owned(), "count".to_owned()], "4");
+        toml_set_path(&mut value, &["flag".to_owned()], "true");
+        toml_set_path(&mut value, &["name".to_owned()], "bar");
+
+        assert_eq!(value["nested"]["count"].as_integer(), Some(4));
+        assert_eq!(value["flag"].as_bool(), Some(true));
+        assert_eq!(value["name"].as_str(), Some("bar"));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_set_path_guesses_scalar_kind_and_nests() {
+        let mut value = serde_yaml::Value::Mapping(Default::default());
+        yaml_set_path(&mut value, &["nested".to_owned(), "count".to_owned()], "4");
+        yaml_set_path(&mut value, &["flag".to_owned()], "true");
+        yaml_set_path(&mut value, &["name".to_owned()], "bar");
+
+        assert_eq!(value["nested"]["count"].as_i64(), Some(4));
+        assert_eq!(value["flag"].as_bool(), Some(true));
+        assert_eq!(value["name"].as_str(), Some("bar"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_set_path_guesses_scalar_kind_and_nests() {
+        let mut value = serde_json::Value::Object(Default::default());
+        json_set_path(&mut value, &["nested".to_owned(), "count".to_owned()], "4");
+        json_set_path(&mut value, &["flag".to_owned()], "true");
+        json_set_path(&mut value, &["name".to_owned()], "bar");
+
+        assert_eq!(value["nested"]["count"], 4);
+        assert_eq!(value["flag"], true);
+        assert_eq!(value["name"], "bar");
+    }
+
+    #[cfg(feature = "ron")]
+    #[test]
+    fn ron_set_path_guesses_scalar_kind_and_nests() {
+        let mut value = ron::Value::Map(Default::default());
+        ron_set_path(&mut value, &["nested".to_owned(), "count".to_owned()], "4");
+        ron_set_path(&mut value, &["flag".to_owned()], "true");
+
+        let nested = match &value {
+            ron::Value::Map(map) => map.get(&ron::Value::String("nested".to_owned())).unwrap(),
+            _ => panic!("expected a ron map"),
+        };
+        assert_eq!(
+            nested,
+            &ron::Value::Map({
+                let mut inner = ron::Map::new();
+                inner.insert(
+                    ron::Value::String("count".to_owned()),
+                    ron::Value::Number(ron::Number::from(4)),
+                );
+                inner
+            })
+        );
+    }
+
+    #[test]
+    fn matching_env_vars_filters_by_prefix_and_splits_nested_fields() {
+        std::env::set_var("CFGEN_TEST_MATCH_NESTED__FIELD", "42");
+        std::env::set_var("CFGEN_TEST_MATCHING_OTHER", "ignored");
+
+        let matches = matching_env_vars("CFGEN_TEST_MATCH");
+
+        std::env::remove_var("CFGEN_TEST_MATCH_NESTED__FIELD");
+        std::env::remove_var("CFGEN_TEST_MATCHING_OTHER");
+
+        assert_eq!(matches.len(), 1);
+        let nested = matches
+            .iter()
+            .find(|(key, ..)| key == "CFGEN_TEST_MATCH_NESTED__FIELD")
+            .expect("nested field var should be present");
+        assert_eq!(nested.1, vec!["nested".to_owned(), "field".to_owned()]);
+        assert_eq!(nested.2, "42");
+    }
+}
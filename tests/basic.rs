@@ -1,7 +1,7 @@
 extern crate cfgen;
 
 use cfgen::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 // TODO: real tests
 
@@ -30,9 +30,192 @@ struct _YamlTest {
     _b: String,
 }
 
+const _JSON_DEFAULT: &str = r#"{"_b": "test"}"#;
+
+#[cfg(feature = "json")]
+#[derive(Cfgen, Deserialize)]
+#[cfgen(app_name = "test", default = "_JSON_DEFAULT", format = "json")]
+struct _JsonTest {
+    _b: String,
+}
+
+const _RON_DEFAULT: &str = r#"(
+    _b: "test",
+)"#;
+
+#[cfg(feature = "ron")]
+#[derive(Cfgen, Deserialize)]
+#[cfgen(app_name = "test", default = "_RON_DEFAULT", format = "ron")]
+struct _RonTest {
+    _b: String,
+}
+
 #[test]
 #[cfg(feature = "with-toml")]
 fn cfgen_implemented() {
     let _ = || _TomlTest::load();
     let _ = || _TomlTest::load_or_write_default();
 }
+
+#[test]
+#[cfg(feature = "json")]
+fn cfgen_json_implemented() {
+    let _ = || _JsonTest::load();
+    let _ = || _JsonTest::load_or_write_default();
+}
+
+#[test]
+#[cfg(feature = "ron")]
+fn cfgen_ron_implemented() {
+    let _ = || _RonTest::load();
+    let _ = || _RonTest::load_or_write_default();
+}
+
+#[cfg(feature = "with-toml")]
+#[derive(Cfgen, Deserialize, Serialize)]
+#[cfgen(app_name = "test", default = "_TOML_DEFAULT", format = "toml", store)]
+struct _StoreTest {
+    _b: String,
+}
+
+#[test]
+#[cfg(feature = "with-toml")]
+fn cfgen_store_implemented() {
+    let _ = |c: &_StoreTest| c.store();
+}
+
+#[cfg(feature = "with-toml")]
+#[derive(Cfgen, Deserialize)]
+#[cfgen(
+    app_name = "cfgen-layered-test",
+    org = "cfgen-test",
+    qualifier = "rs",
+    filename = "cfgen_layered_test.toml",
+    format = "toml",
+    layered
+)]
+struct _LayeredTest {
+    _a: String,
+    _b: String,
+}
+
+#[test]
+#[cfg(feature = "with-toml")]
+fn cfgen_layered_merges_with_local_overriding_user() {
+    let user_path = _LayeredTest::path();
+    std::fs::create_dir_all(user_path.parent().unwrap()).unwrap();
+    std::fs::write(user_path, "_a = \"user\"\n_b = \"user\"\n").unwrap();
+
+    let local_path = std::env::current_dir()
+        .unwrap()
+        .join("cfgen_layered_test.toml");
+    std::fs::write(&local_path, "_b = \"local\"\n").unwrap();
+
+    let result = _LayeredTest::load_layered();
+
+    std::fs::remove_file(user_path).ok();
+    std::fs::remove_file(&local_path).ok();
+
+    let (config, used) = result.unwrap();
+    // Only the user and local layers exist; the system layer is skipped, and the local layer
+    // overrides just the key it sets, leaving the rest of the user layer intact.
+    assert_eq!(config._a, "user");
+    assert_eq!(config._b, "local");
+    assert!(used.contains(&user_path.to_owned()));
+    assert!(used.contains(&local_path));
+}
+
+const _ENV_TOML_DEFAULT: &str = r#"
+_b = "file"
+_n = 1
+"#;
+
+#[cfg(feature = "with-toml")]
+#[derive(Cfgen, Deserialize)]
+#[cfgen(
+    app_name = "test-env",
+    org = "cfgen-test",
+    qualifier = "rs",
+    filename = "cfgen_env_test.toml",
+    default = "_ENV_TOML_DEFAULT",
+    format = "toml",
+    env_prefix = "CFGEN_TEST"
+)]
+struct _EnvTest {
+    _b: String,
+    _n: i32,
+}
+
+#[test]
+#[cfg(feature = "with-toml")]
+fn cfgen_env_overrides_and_blames_the_breaking_variable() {
+    let path = _EnvTest::path();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, _ENV_TOML_DEFAULT).unwrap();
+
+    // A single override is applied on top of the file.
+    std::env::set_var("CFGEN_TEST__B", "overridden");
+    let config = _EnvTest::load_with_env().unwrap();
+    assert_eq!(config._b, "overridden");
+    assert_eq!(config._n, 1);
+    std::env::remove_var("CFGEN_TEST__B");
+
+    // When one override is harmless and another makes deserialization fail, the error blames
+    // the one that actually broke it, not whichever happened to be applied first.
+    std::env::set_var("CFGEN_TEST__B", "still fine");
+    std::env::set_var("CFGEN_TEST__N", "not a number");
+    let result = _EnvTest::load_with_env();
+    std::env::remove_var("CFGEN_TEST__B");
+    std::env::remove_var("CFGEN_TEST__N");
+    std::fs::remove_file(path).ok();
+
+    match result {
+        Err(::cfgen::Error::Env { var, .. }) => assert_eq!(var, "CFGEN_TEST__N"),
+        other => panic!("expected Error::Env blaming CFGEN_TEST__N, got {:?}", other),
+    }
+}
+
+const _ALIAS_TOML_DEFAULT: &str = r#"
+_b = "default"
+"#;
+
+#[cfg(feature = "with-toml")]
+#[derive(Cfgen, Deserialize)]
+#[cfgen(
+    app_name = "test-alias",
+    org = "cfgen-test",
+    qualifier = "rs",
+    filename = "cfgen_alias_test.toml",
+    default = "_ALIAS_TOML_DEFAULT",
+    format = "toml",
+    aliases = "cfgen_alias_test_legacy.toml"
+)]
+struct _AliasTest {
+    _b: String,
+}
+
+#[test]
+#[cfg(feature = "with-toml")]
+fn cfgen_aliases_detects_lone_alias_and_ambiguity() {
+    let primary = _AliasTest::path();
+    let legacy = primary.with_file_name("cfgen_alias_test_legacy.toml");
+    std::fs::create_dir_all(primary.parent().unwrap()).unwrap();
+    std::fs::remove_file(primary).ok();
+    std::fs::remove_file(&legacy).ok();
+
+    // A lone legacy alias is loaded as-is, not silently replaced by the default.
+    std::fs::write(&legacy, "_b = \"legacy\"\n").unwrap();
+    let (load, config) = _AliasTest::load_or_write_default().unwrap();
+    assert!(matches!(load, ::cfgen::ConfigLoad::Loaded));
+    assert_eq!(config._b, "legacy");
+    assert!(!primary.is_file());
+
+    // Once both the primary filename and an alias are present, the ambiguity is reported
+    // instead of silently preferring one over the other.
+    std::fs::write(primary, _ALIAS_TOML_DEFAULT).unwrap();
+    let result = _AliasTest::load_or_write_default();
+    assert!(matches!(result, Err(::cfgen::Error::AmbiguousSource(..))));
+
+    std::fs::remove_file(primary).ok();
+    std::fs::remove_file(&legacy).ok();
+}